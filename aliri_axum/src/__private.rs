@@ -0,0 +1,147 @@
+//! Implementation details used by the macros in this crate.
+//!
+//! Everything in this module is exempt from this crate's semver guarantees.
+
+use axum::extract::RequestParts;
+
+pub use aliri_oauth2::oauth2::HasScope;
+pub use aliri_oauth2::oauth2::ScopePolicy;
+pub use once_cell::sync::OnceCell;
+
+pub use crate::expr::EndpointScopeExprPolicy;
+pub use crate::ScopeExpr;
+
+use crate::{AuthFailed, Challenge, HasRoles, RoleMapping};
+
+/// Common interface for the challenge a guard presents on rejection.
+///
+/// Implemented by the guards that [`scope_guard!`](crate::scope_guard!),
+/// [`scope_guards!`](crate::scope_guards!),
+/// [`dynamic_scope_guard!`](crate::dynamic_scope_guard!),
+/// [`role_guard!`](crate::role_guard!) and
+/// [`roles_guards!`](crate::roles_guards!) produce, so that combinators like
+/// [`claim_guard!`](crate::claim_guard!) can thread the wrapped guard's
+/// configured challenge through without needing to know which macro -- and
+/// therefore which `EndpointXPolicy` trait, if any -- produced it. Guards
+/// produced by `claim_guard!` itself do not implement it.
+pub trait HasChallenge {
+    /// Returns the challenge presented to clients that fail this guard's
+    /// requirements.
+    fn challenge() -> &'static Challenge;
+}
+
+/// Extracts claims of type `C` from the request's extensions and checks them
+/// against `policy`, producing an [`AuthFailed`] tailored to `challenge` on
+/// failure.
+pub fn from_request<B, C>(
+    req: &mut RequestParts<B>,
+    policy: &'static ScopePolicy,
+    challenge: &'static Challenge,
+) -> Result<C, AuthFailed>
+where
+    B: Send,
+    C: HasScope + Clone + Send + Sync + 'static,
+{
+    let claims = req
+        .extensions()
+        .get::<C>()
+        .cloned()
+        .ok_or(AuthFailed::MissingClaims { challenge })?;
+
+    if policy.authorize(claims.scope()) {
+        Ok(claims)
+    } else {
+        Err(AuthFailed::InsufficientScopes { policy, challenge })
+    }
+}
+
+/// Like [`from_request`], but checks against a scope computed for this
+/// particular request rather than a policy fixed at compile time.
+pub fn from_request_dynamic<B, C>(
+    req: &mut RequestParts<B>,
+    required_scope: String,
+    challenge: &'static Challenge,
+) -> Result<C, AuthFailed>
+where
+    B: Send,
+    C: HasScope + Clone + Send + Sync + 'static,
+{
+    let claims = req
+        .extensions()
+        .get::<C>()
+        .cloned()
+        .ok_or(AuthFailed::MissingClaims { challenge })?;
+
+    let scope = required_scope.parse().map_err(|_| AuthFailed::ExtractionFailed {
+        reason: format!("the computed scope {required_scope:?} is not a valid scope"),
+    })?;
+    let policy = ScopePolicy::deny_all().or_allow(scope);
+
+    if policy.authorize(claims.scope()) {
+        Ok(claims)
+    } else {
+        Err(AuthFailed::DynamicInsufficientScope {
+            required_scope,
+            challenge,
+        })
+    }
+}
+
+/// Extracts claims of type `C` from the request's extensions and checks that
+/// they hold at least one of `required_roles`, after translating the raw
+/// roles reported by the claims through `mapping`.
+pub fn from_request_roles<B, C>(
+    req: &mut RequestParts<B>,
+    required_roles: &'static [&'static str],
+    mapping: &'static RoleMapping,
+    challenge: &'static Challenge,
+) -> Result<C, AuthFailed>
+where
+    B: Send,
+    C: HasRoles + Clone + Send + Sync + 'static,
+{
+    let claims = req
+        .extensions()
+        .get::<C>()
+        .cloned()
+        .ok_or(AuthFailed::MissingClaims { challenge })?;
+
+    let is_authorized = claims
+        .roles()
+        .iter()
+        .filter_map(|raw_role| mapping.map(raw_role))
+        .any(|role| required_roles.contains(&role));
+
+    if is_authorized {
+        Ok(claims)
+    } else {
+        Err(AuthFailed::InsufficientRoles {
+            required_roles,
+            challenge,
+        })
+    }
+}
+
+/// Extracts claims of type `C` from the request's extensions and evaluates
+/// `expr` against the claims' held scope.
+pub fn from_request_expr<B, C>(
+    req: &mut RequestParts<B>,
+    expr: &'static ScopeExpr,
+    challenge: &'static Challenge,
+) -> Result<C, AuthFailed>
+where
+    B: Send,
+    C: HasScope + Clone + Send + Sync + 'static,
+{
+    let claims = req
+        .extensions()
+        .get::<C>()
+        .cloned()
+        .ok_or(AuthFailed::MissingClaims { challenge })?;
+
+    if expr.evaluate(claims.scope()) {
+        Ok(claims)
+    } else {
+        Err(AuthFailed::ScopeExprNotSatisfied { expr, challenge })
+    }
+}
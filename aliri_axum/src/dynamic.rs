@@ -0,0 +1,226 @@
+//! A scope guard whose required scope is computed from the request itself.
+
+/// Constructs an extractor that asserts a provided token has a scope that is
+/// computed from data extracted out of the request, rather than one that is
+/// fixed at compile time.
+///
+/// This is useful for resource servers where the scope depends on the
+/// resource being accessed, such as a Docker-registry-style
+/// `repository:<name>:pull` scope.
+///
+/// ```
+/// use aliri_axum::dynamic_scope_guard;
+/// use axum::extract::Path;
+///
+/// dynamic_scope_guard!(
+///     RepoPull;
+///     |params: Path<(String, String)>| format!("repository:{}/{}:pull", params.0.0, params.0.1)
+/// );
+/// ```
+///
+/// As with [`scope_guard!`](crate::scope_guard!), a custom claims type and a
+/// realm for the `WWW-Authenticate` challenge can both be configured:
+///
+/// ```
+/// use aliri_axum::dynamic_scope_guard;
+/// use axum::extract::Path;
+///
+/// dynamic_scope_guard!(
+///     RepoPull(::aliri_oauth2::oauth2::BasicClaimsWithScope);
+///     |params: Path<(String, String)>| format!("repository:{}/{}:pull", params.0.0, params.0.1);
+///     realm = "registry"
+/// );
+/// ```
+///
+/// The closure-like expression is evaluated once per request, after the
+/// parameter extractor has run but before the scope check, so it may
+/// reference any data extracted from `params`.
+#[macro_export]
+macro_rules! dynamic_scope_guard {
+    ($vis:vis $i:ident; |$param:ident : $param_ty:ty| $body:expr $(; realm = $realm:literal)?) => {
+        $crate::dynamic_scope_guard!(
+            $vis $i(::aliri_oauth2::oauth2::BasicClaimsWithScope);
+            |$param: $param_ty| $body
+            $(; realm = $realm)?
+        );
+    };
+    ($vis:vis $i:ident($claim:ty); |$param:ident : $param_ty:ty| $body:expr $(; realm = $realm:literal)?) => {
+        #[doc = "Ensures that a claims object authorizes access to a scope computed from the request."]
+        #[doc = ""]
+        #[doc = "When the claims lack the computed scope, the `WWW-Authenticate` challenge names it. "]
+        #[doc = "[`aliri_axum::VerboseAuthxErrors`] in the `extensions` of the request does not change the response."]
+        $vis struct $i($vis $claim);
+
+        impl $i {
+            #[allow(dead_code)]
+            $vis fn into_claims(self) -> $claim {
+                self.0
+            }
+
+            #[allow(dead_code)]
+            $vis fn claims(&self) -> &$claim {
+                &self.0
+            }
+
+            fn challenge() -> &'static $crate::Challenge {
+                $(
+                    {
+                        static CHALLENGE: $crate::Challenge = $crate::Challenge::new().with_realm($realm);
+                        return &CHALLENGE;
+                    }
+                )?
+                #[allow(unreachable_code)]
+                {
+                    static CHALLENGE: $crate::Challenge = $crate::Challenge::new();
+                    &CHALLENGE
+                }
+            }
+        }
+
+        impl $crate::__private::HasChallenge for $i {
+            fn challenge() -> &'static $crate::Challenge {
+                Self::challenge()
+            }
+        }
+
+        #[::axum::async_trait]
+        impl<B> ::axum::extract::FromRequest<B> for $i
+        where
+            B: Send,
+            $param_ty: ::axum::extract::FromRequest<B>,
+            <$param_ty as ::axum::extract::FromRequest<B>>::Rejection: ::std::fmt::Debug,
+        {
+            type Rejection = $crate::AuthFailed;
+
+            async fn from_request(
+                req: &mut ::axum::extract::RequestParts<B>,
+            ) -> Result<Self, Self::Rejection> {
+                let $param: $param_ty = ::axum::extract::FromRequest::from_request(req)
+                    .await
+                    .map_err(|err| $crate::AuthFailed::ExtractionFailed {
+                        reason: ::std::format!(
+                            "failed to extract {}: {:?}",
+                            ::std::stringify!($param_ty),
+                            err,
+                        ),
+                    })?;
+
+                let required_scope: ::std::string::String = $body;
+
+                $crate::__private::from_request_dynamic(req, required_scope, Self::challenge())
+                    .map(Self)
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use aliri_oauth2::{oauth2, scope};
+    use axum::{
+        async_trait,
+        extract::{FromRequest, RequestParts},
+        http::Request,
+    };
+
+    use crate::AuthFailed;
+
+    #[derive(Clone)]
+    struct RepoParams(String, String);
+
+    #[async_trait]
+    impl<B: Send> FromRequest<B> for RepoParams {
+        type Rejection = std::convert::Infallible;
+
+        async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+            Ok(req.extensions().get::<RepoParams>().cloned().unwrap())
+        }
+    }
+
+    dynamic_scope_guard!(
+        RepoPull(MyClaims);
+        |params: RepoParams| format!("repository:{}/{}:pull", params.0, params.1)
+    );
+
+    #[derive(Clone)]
+    struct MyClaims(oauth2::Scope);
+
+    impl oauth2::HasScope for MyClaims {
+        fn scope(&self) -> &oauth2::Scope {
+            &self.0
+        }
+    }
+
+    fn request_with(params: RepoParams, claim_scope: oauth2::Scope) -> RequestParts<()> {
+        let mut req = RequestParts::new(Request::new(()));
+        req.extensions_mut().insert(params);
+        req.extensions_mut().insert(MyClaims(claim_scope));
+        req
+    }
+
+    #[tokio::test]
+    async fn repo_pull_guard_with_matching_scope() {
+        let mut req = request_with(
+            RepoParams("myorg".into(), "myimage".into()),
+            scope!["repository:myorg/myimage:pull"].unwrap(),
+        );
+        RepoPull::from_request(&mut req).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn repo_pull_guard_with_scope_for_different_repo() {
+        let mut req = request_with(
+            RepoParams("myorg".into(), "myimage".into()),
+            scope!["repository:otherorg/otherimage:pull"].unwrap(),
+        );
+        match RepoPull::from_request(&mut req).await {
+            Err(AuthFailed::DynamicInsufficientScope { required_scope, .. }) => {
+                assert_eq!(required_scope, "repository:myorg/myimage:pull");
+            }
+            other => panic!("expected DynamicInsufficientScope, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[tokio::test]
+    async fn repo_pull_guard_without_claims() {
+        let mut req = RequestParts::new(Request::new(()));
+        req.extensions_mut()
+            .insert(RepoParams("myorg".into(), "myimage".into()));
+
+        match RepoPull::from_request(&mut req).await {
+            Err(AuthFailed::MissingClaims { .. }) => {}
+            other => panic!("expected MissingClaims, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[derive(Debug)]
+    struct AlwaysFailParamsRejection;
+
+    struct AlwaysFailParams;
+
+    #[async_trait]
+    impl<B: Send> FromRequest<B> for AlwaysFailParams {
+        type Rejection = AlwaysFailParamsRejection;
+
+        async fn from_request(_req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+            Err(AlwaysFailParamsRejection)
+        }
+    }
+
+    dynamic_scope_guard!(
+        AlwaysFail(MyClaims);
+        |_params: AlwaysFailParams| "irrelevant".to_string()
+    );
+
+    #[tokio::test]
+    async fn guard_with_failing_param_extractor_reports_extraction_failed() {
+        let mut req = RequestParts::new(Request::new(()));
+        req.extensions_mut()
+            .insert(MyClaims(scope!["anything"].unwrap()));
+
+        match AlwaysFail::from_request(&mut req).await {
+            Err(AuthFailed::ExtractionFailed { .. }) => {}
+            other => panic!("expected ExtractionFailed, got {:?}", other.map(|_| ())),
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+
+use crate::__private::ScopePolicy;
+use crate::{Challenge, ScopeExpr};
+
+/// The rejection produced when a request fails to satisfy a guard's
+/// authorization requirements.
+///
+/// Renders an RFC 6750-compliant `WWW-Authenticate` challenge, so that
+/// well-behaved OAuth2/Bearer clients can tell a missing token apart from a
+/// token that lacks the necessary scope.
+#[derive(Debug)]
+pub enum AuthFailed {
+    /// No validated claims were found in the request's extensions.
+    ///
+    /// This usually means the authority middleware that validates and
+    /// inserts the claims wasn't run ahead of this guard.
+    MissingClaims {
+        /// The challenge to present to the client.
+        challenge: &'static Challenge,
+    },
+    /// Claims were present, but did not satisfy the guard's scope policy.
+    InsufficientScopes {
+        /// The policy that was not satisfied.
+        policy: &'static ScopePolicy,
+        /// The challenge to present to the client.
+        challenge: &'static Challenge,
+    },
+    /// Claims were present, but did not satisfy a scope that was computed
+    /// from the request itself, such as by [`dynamic_scope_guard!`](crate::dynamic_scope_guard!).
+    DynamicInsufficientScope {
+        /// The scope that was required for this particular request.
+        required_scope: String,
+        /// The challenge to present to the client.
+        challenge: &'static Challenge,
+    },
+    /// Claims were present, but held none of the roles required by a
+    /// [`role_guard!`](crate::role_guard!).
+    InsufficientRoles {
+        /// The roles, any one of which would have satisfied the guard.
+        required_roles: &'static [&'static str],
+        /// The challenge to present to the client.
+        challenge: &'static Challenge,
+    },
+    /// Claims were present, but did not satisfy the boolean scope expression
+    /// required by the expression-grammar form of
+    /// [`scope_guard!`](crate::scope_guard!).
+    ScopeExprNotSatisfied {
+        /// The expression that was not satisfied.
+        expr: &'static ScopeExpr,
+        /// The challenge to present to the client.
+        challenge: &'static Challenge,
+    },
+    /// A custom predicate attached via [`claim_guard!`](crate::claim_guard!)
+    /// rejected the request.
+    PredicateRejected {
+        /// A description of which rule rejected the request.
+        reason: String,
+        /// The challenge to present to the client.
+        challenge: &'static Challenge,
+    },
+    /// An extractor that a guard depends on -- such as a path or query
+    /// extractor used by [`dynamic_scope_guard!`](crate::dynamic_scope_guard!)
+    /// or the extra extractor of a [`claim_guard!`](crate::claim_guard!) --
+    /// failed to extract from the request, or the scope that a
+    /// [`dynamic_scope_guard!`](crate::dynamic_scope_guard!) computed from
+    /// the request is not a valid scope.
+    ///
+    /// This is not itself an authorization failure, so unlike the other
+    /// variants it carries no challenge; the request was malformed or
+    /// otherwise couldn't be routed, independent of the caller's token.
+    ExtractionFailed {
+        /// A description of what failed to extract.
+        reason: String,
+    },
+}
+
+/// Escapes a value for safe inclusion inside an HTTP quoted-string (RFC
+/// 7230 section 3.2.6): backslash and double-quote are backslash-escaped,
+/// and control characters -- which a quoted-string can't contain at all --
+/// are stripped.
+///
+/// Used wherever a challenge parameter is built from request-supplied data
+/// (such as [`DynamicInsufficientScope`](AuthFailed::DynamicInsufficientScope)'s
+/// `required_scope`), which can't be trusted to already be a well-formed
+/// header value.
+fn escape_quoted_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars().filter(|c| !c.is_control()) {
+        if c == '"' || c == '\\' {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+impl IntoResponse for AuthFailed {
+    fn into_response(self) -> Response {
+        match self {
+            AuthFailed::MissingClaims { challenge } => {
+                let www_authenticate = format!(
+                    "Bearer realm=\"{}\", error=\"invalid_token\"",
+                    challenge.realm(),
+                );
+                (
+                    StatusCode::UNAUTHORIZED,
+                    [(header::WWW_AUTHENTICATE, www_authenticate)],
+                )
+                    .into_response()
+            }
+            AuthFailed::InsufficientScopes { policy, .. } => {
+                let www_authenticate = format!(
+                    "Bearer error=\"insufficient_scope\", error_description=\"the presented token does not have the required scope\", scope=\"{}\"",
+                    policy,
+                );
+                (
+                    StatusCode::FORBIDDEN,
+                    [(header::WWW_AUTHENTICATE, www_authenticate)],
+                )
+                    .into_response()
+            }
+            AuthFailed::DynamicInsufficientScope { required_scope, .. } => {
+                let www_authenticate = format!(
+                    "Bearer error=\"insufficient_scope\", error_description=\"the presented token does not have the required scope\", scope=\"{}\"",
+                    escape_quoted_string(&required_scope),
+                );
+                (
+                    StatusCode::FORBIDDEN,
+                    [(header::WWW_AUTHENTICATE, www_authenticate)],
+                )
+                    .into_response()
+            }
+            AuthFailed::InsufficientRoles { required_roles, .. } => {
+                let www_authenticate = format!(
+                    "Bearer error=\"insufficient_scope\", error_description=\"requires one of the following roles: {}\"",
+                    required_roles.join(", "),
+                );
+                (
+                    StatusCode::FORBIDDEN,
+                    [(header::WWW_AUTHENTICATE, www_authenticate)],
+                )
+                    .into_response()
+            }
+            AuthFailed::ScopeExprNotSatisfied { expr, .. } => {
+                let www_authenticate = format!(
+                    "Bearer error=\"insufficient_scope\", error_description=\"the presented token does not satisfy the required scope expression\", scope=\"{}\"",
+                    expr.literals().join(" "),
+                );
+                (
+                    StatusCode::FORBIDDEN,
+                    [(header::WWW_AUTHENTICATE, www_authenticate)],
+                )
+                    .into_response()
+            }
+            AuthFailed::PredicateRejected { reason, .. } => {
+                let www_authenticate = format!(
+                    "Bearer error=\"insufficient_scope\", error_description=\"rejected by rule: {}\"",
+                    reason,
+                );
+                (
+                    StatusCode::FORBIDDEN,
+                    [(header::WWW_AUTHENTICATE, www_authenticate)],
+                )
+                    .into_response()
+            }
+            AuthFailed::ExtractionFailed { reason } => {
+                (StatusCode::BAD_REQUEST, reason).into_response()
+            }
+        }
+    }
+}
@@ -0,0 +1,105 @@
+//! Runtime representation of a full boolean expression over scope literals.
+
+use aliri_oauth2::oauth2::Scope;
+
+use crate::Challenge;
+
+/// A boolean expression over scope literals, supporting `&&`, `||`, and `!`
+/// (negation).
+///
+/// Built by the boolean-expression form of
+/// [`scope_guard!`](crate::scope_guard!) and evaluated against a token's
+/// held scope at request time, rather than being precomputed into a
+/// [`ScopePolicy`](crate::__private::ScopePolicy) of OR-of-AND-sets. This is
+/// what makes negation -- "deny if this scope is present" -- expressible.
+#[derive(Debug)]
+pub enum ScopeExpr {
+    /// True if `scope` is held by the presented token.
+    Literal(&'static str),
+    /// True if the inner expression is false.
+    Not(&'static ScopeExpr),
+    /// True if both inner expressions are true.
+    And(&'static ScopeExpr, &'static ScopeExpr),
+    /// True if either inner expression is true.
+    Or(&'static ScopeExpr, &'static ScopeExpr),
+}
+
+impl ScopeExpr {
+    /// Evaluates this expression against a token's held scope.
+    pub fn evaluate(&self, held: &Scope) -> bool {
+        match self {
+            Self::Literal(scope) => held.contains(scope),
+            Self::Not(inner) => !inner.evaluate(held),
+            Self::And(lhs, rhs) => lhs.evaluate(held) && rhs.evaluate(held),
+            Self::Or(lhs, rhs) => lhs.evaluate(held) || rhs.evaluate(held),
+        }
+    }
+
+    /// Collects the distinct scope literals appearing anywhere in this
+    /// expression, in the order they first appear, negated literals prefixed
+    /// with `!`.
+    ///
+    /// Used to render a plain, space-separated `scope` value for a
+    /// `WWW-Authenticate` challenge -- [`Display`](std::fmt::Display) isn't
+    /// suitable for that, since it renders the full AST, quoting and all.
+    /// Negation is preserved rather than collapsed into the positive form,
+    /// since a literal that's only ever negated is one the client must *not*
+    /// hold, the opposite of what an unmarked scope name would suggest.
+    pub fn literals(&self) -> Vec<String> {
+        let mut literals = Vec::new();
+        self.collect_literals(false, &mut literals);
+        literals
+    }
+
+    fn collect_literals(&self, negated: bool, literals: &mut Vec<String>) {
+        match self {
+            Self::Literal(scope) => {
+                let rendered = if negated {
+                    format!("!{scope}")
+                } else {
+                    (*scope).to_owned()
+                };
+                if !literals.contains(&rendered) {
+                    literals.push(rendered);
+                }
+            }
+            Self::Not(inner) => inner.collect_literals(!negated, literals),
+            Self::And(lhs, rhs) | Self::Or(lhs, rhs) => {
+                lhs.collect_literals(negated, literals);
+                rhs.collect_literals(negated, literals);
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for ScopeExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Literal(scope) => write!(f, "\"{scope}\""),
+            Self::Not(inner) => write!(f, "!{inner}"),
+            Self::And(lhs, rhs) => write!(f, "({lhs} && {rhs})"),
+            Self::Or(lhs, rhs) => write!(f, "({lhs} || {rhs})"),
+        }
+    }
+}
+
+/// Associates a guard type with the [`ScopeExpr`] it enforces.
+///
+/// Implemented automatically for guards produced by the boolean-expression
+/// form of [`scope_guard!`](crate::scope_guard!).
+pub trait EndpointScopeExprPolicy {
+    /// The claims type extracted and checked by this guard.
+    type Claims;
+
+    /// Returns the scope expression enforced by this guard.
+    fn scope_expr() -> &'static ScopeExpr;
+
+    /// Returns the challenge presented to clients that fail this guard's
+    /// requirements.
+    ///
+    /// Defaults to a challenge with no realm configured.
+    fn challenge() -> &'static Challenge {
+        static DEFAULT: Challenge = Challenge::new();
+        &DEFAULT
+    }
+}
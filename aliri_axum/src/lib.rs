@@ -0,0 +1,38 @@
+//! Axum integration for `aliri`.
+//!
+//! This crate provides extractor-based guards that can be attached directly
+//! to handler arguments in order to assert that a presented JWT has already
+//! been validated and satisfies some additional authorization requirement,
+//! such as holding a particular OAuth2 scope.
+//!
+//! See [`scope_guard!`] for the most common entry point.
+
+// Lets the `::aliri_axum` paths emitted by `scope_expr_guard!` resolve inside
+// this crate as well.
+extern crate self as aliri_axum;
+
+mod dynamic;
+mod error;
+mod expr;
+mod macros;
+mod policy;
+mod predicate;
+mod roles;
+
+#[doc(hidden)]
+pub mod __private;
+
+#[doc(hidden)]
+pub use aliri_axum_macros::scope_expr_guard;
+
+pub use error::AuthFailed;
+pub use expr::{EndpointScopeExprPolicy, ScopeExpr};
+pub use policy::{Challenge, EndpointScopePolicy};
+pub use roles::{EndpointRolePolicy, HasRoles, RoleMapping};
+
+/// A marker type for a request's extensions.
+///
+/// No guard in this crate reads it: [`AuthFailed`] renders the same response
+/// whether or not it is present.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct VerboseAuthxErrors;
@@ -82,6 +82,26 @@
 /// # }
 /// ```
 ///
+/// A realm can also be configured, which will be reported as part of the
+/// `WWW-Authenticate` challenge returned when the guard rejects a request:
+///
+/// ```
+/// use aliri_axum::scope_guard;
+///
+/// scope_guard!(AdminOnly; "admin"; realm = "my-api");
+/// ```
+///
+/// For requirements that the DNF grammar above can't express -- most notably
+/// negation, e.g. "must not be a restricted token" -- a full boolean
+/// expression over scope literals can be used instead, supporting `&&`,
+/// `||`, parentheses, and `!`:
+///
+/// ```
+/// use aliri_axum::scope_guard;
+///
+/// scope_guard!(ReadNotRevoked; ("read" && !"revoked") || "admin");
+/// ```
+///
 /// A custom claim type can be used in order to better use the validated data:
 ///
 /// ```
@@ -124,20 +144,23 @@
 ///     "You're an admin!"
 /// }
 /// ```
-// This would probably work nicer as a procedural macro, as then it could
-// produce even better documentation.
+// The DNF forms below are handled directly by `macro_rules!`; anything that
+// doesn't fit that grammar (parenthesized boolean expressions, negation)
+// falls through to the `scope_expr_guard!` proc macro at the bottom of this
+// definition, which can produce much better error messages for malformed
+// expressions than a `macro_rules!` parser ever could.
 #[macro_export]
 macro_rules! scope_guard {
-    ($vis:vis $i:ident; $scope:literal) => {
-        $crate::scope_guard!($vis $i; [$scope]);
+    ($vis:vis $i:ident; $scope:literal $(; realm = $realm:literal)?) => {
+        $crate::scope_guard!($vis $i; [$scope] $(; realm = $realm)?);
     };
-    ($vis:vis $i:ident; [$($scope:literal)||* $(,)?]) => {
-        $crate::scope_guard!($vis $i(::aliri_oauth2::oauth2::BasicClaimsWithScope); [$($scope)||*]);
+    ($vis:vis $i:ident; [$($scope:literal)||* $(,)?] $(; realm = $realm:literal)?) => {
+        $crate::scope_guard!($vis $i(::aliri_oauth2::oauth2::BasicClaimsWithScope); [$($scope)||*] $(; realm = $realm)?);
     };
-    ($vis:vis $i:ident($claim:ty); $scope:literal) => {
-        $crate::scope_guard!($vis $i($claim); [$scope]);
+    ($vis:vis $i:ident($claim:ty); $scope:literal $(; realm = $realm:literal)?) => {
+        $crate::scope_guard!($vis $i($claim); [$scope] $(; realm = $realm)?);
     };
-    ($vis:vis $i:ident($claim:ty); [$($scope:literal)||* $(,)?]) => {
+    ($vis:vis $i:ident($claim:ty); [$($scope:literal)||* $(,)?] $(; realm = $realm:literal)?) => {
         #[doc = "Ensures that a claims object authorizes access to a given scope"]
         #[doc = ""]
         #[doc = "The claims object must have one of the following sets of scopes to be considered authorized."]
@@ -175,6 +198,19 @@ macro_rules! scope_guard {
                     )*
                 })
             }
+
+            $(
+                fn challenge() -> &'static $crate::Challenge {
+                    static CHALLENGE: $crate::Challenge = $crate::Challenge::new().with_realm($realm);
+                    &CHALLENGE
+                }
+            )?
+        }
+
+        impl $crate::__private::HasChallenge for $i {
+            fn challenge() -> &'static $crate::Challenge {
+                <Self as $crate::EndpointScopePolicy>::challenge()
+            }
         }
 
         #[::axum::async_trait]
@@ -187,10 +223,21 @@ macro_rules! scope_guard {
             async fn from_request(
                 req: &mut ::axum::extract::RequestParts<B>,
             ) -> Result<Self, Self::Rejection> {
-                $crate::__private::from_request(req, <Self as $crate::EndpointScopePolicy>::scope_policy()).map(Self)
+                $crate::__private::from_request(
+                    req,
+                    <Self as $crate::EndpointScopePolicy>::scope_policy(),
+                    <Self as $crate::EndpointScopePolicy>::challenge(),
+                )
+                .map(Self)
             }
         }
     };
+    ($vis:vis $i:ident; $expr:expr $(; realm = $realm:literal)?) => {
+        $crate::scope_guard!($vis $i(::aliri_oauth2::oauth2::BasicClaimsWithScope); $expr $(; realm = $realm)?);
+    };
+    ($vis:vis $i:ident($claim:ty); $expr:expr $(; realm = $realm:literal)?) => {
+        $crate::scope_expr_guard!($vis $i($claim); $expr $(; realm = $realm)?);
+    };
 }
 
 /// Convenience macro for services that need to define many scopes.
@@ -262,10 +309,14 @@ mod tests {
         http::Request,
     };
 
-    use crate::AuthFailed;
+    use crate::{AuthFailed, EndpointScopePolicy};
 
     scope_guard!(AdminOnly(MyClaims); "admin");
 
+    scope_guard!(AdminOnlyWithRealm(MyClaims); "admin"; realm = "my-realm");
+
+    scope_guard!(ReadNotRevoked(MyClaims); ("read" && !"revoked") || "admin");
+
     scope_guards! {
         type Claims = MyClaims;
 
@@ -312,11 +363,38 @@ mod tests {
         request_with_scope(scope!["admin", "testing"].unwrap())
     }
 
+    #[tokio::test]
+    async fn read_not_revoked_scope_guard_with_read_scope() {
+        ReadNotRevoked::from_request(&mut request_with_scope(scope!["read"].unwrap()))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn read_not_revoked_scope_guard_with_read_and_revoked_scope() {
+        match ReadNotRevoked::from_request(&mut request_with_scope(
+            scope!["read", "revoked"].unwrap(),
+        ))
+        .await
+        {
+            Err(AuthFailed::ScopeExprNotSatisfied { .. }) => {}
+            other => panic!("expected ScopeExprNotSatisfied, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[tokio::test]
+    async fn read_not_revoked_scope_guard_with_admin_scope() {
+        ReadNotRevoked::from_request(&mut request_with_admin_scope())
+            .await
+            .unwrap();
+    }
+
     #[tokio::test]
     async fn admin_only_scope_guard_without_claims_returns_error() {
         match AdminOnly::from_request(&mut request_with_no_claims()).await {
-            Err(AuthFailed::MissingClaims) => {}
+            Err(AuthFailed::MissingClaims { .. }) => {}
             Err(AuthFailed::InsufficientScopes { .. }) => panic!("Expected missing claims error"),
+            Err(other) => panic!("Expected missing claims error, got {other:?}"),
             Ok(_) => panic!("Expected AuthFailed"),
         }
     }
@@ -335,11 +413,22 @@ mod tests {
             .unwrap();
     }
 
+    #[tokio::test]
+    async fn admin_only_scope_guard_uses_default_challenge_with_no_realm() {
+        assert_eq!(AdminOnly::challenge().realm(), "");
+    }
+
+    #[tokio::test]
+    async fn admin_only_with_realm_scope_guard_uses_configured_realm() {
+        assert_eq!(AdminOnlyWithRealm::challenge().realm(), "my-realm");
+    }
+
     #[tokio::test]
     async fn admin_only_scope_guard_with_no_scope_claims() {
         match AdminOnly::from_request(&mut request_with_no_scope()).await {
             Err(AuthFailed::InsufficientScopes { .. }) => {}
-            Err(AuthFailed::MissingClaims) => panic!("Expected insufficient scopes error"),
+            Err(AuthFailed::MissingClaims { .. }) => panic!("Expected insufficient scopes error"),
+            Err(other) => panic!("Expected insufficient scopes error, got {other:?}"),
             Ok(_) => panic!("Expected AuthFailed"),
         }
     }
@@ -369,7 +458,8 @@ mod tests {
     async fn testing_scope_guard_with_admin_scope_claims() {
         match Testing::from_request(&mut request_with_admin_scope()).await {
             Err(AuthFailed::InsufficientScopes { .. }) => {}
-            Err(AuthFailed::MissingClaims) => panic!("Expected insufficient scopes error"),
+            Err(AuthFailed::MissingClaims { .. }) => panic!("Expected insufficient scopes error"),
+            Err(other) => panic!("Expected insufficient scopes error, got {other:?}"),
             Ok(_) => panic!("Expected AuthFailed"),
         }
     }
@@ -378,7 +468,8 @@ mod tests {
     async fn testing_admin_scope_guard_with_testing_scope_claims() {
         match TestingAdmin::from_request(&mut request_with_testing_scope()).await {
             Err(AuthFailed::InsufficientScopes { .. }) => {}
-            Err(AuthFailed::MissingClaims) => panic!("Expected insufficient scopes error"),
+            Err(AuthFailed::MissingClaims { .. }) => panic!("Expected insufficient scopes error"),
+            Err(other) => panic!("Expected insufficient scopes error, got {other:?}"),
             Ok(_) => panic!("Expected AuthFailed"),
         }
     }
@@ -387,7 +478,8 @@ mod tests {
     async fn testing_admin_scope_guard_with_admin_scope_claims() {
         match TestingAdmin::from_request(&mut request_with_admin_scope()).await {
             Err(AuthFailed::InsufficientScopes { .. }) => {}
-            Err(AuthFailed::MissingClaims) => panic!("Expected insufficient scopes error"),
+            Err(AuthFailed::MissingClaims { .. }) => panic!("Expected insufficient scopes error"),
+            Err(other) => panic!("Expected insufficient scopes error, got {other:?}"),
             Ok(_) => panic!("Expected AuthFailed"),
         }
     }
@@ -0,0 +1,60 @@
+use crate::__private::ScopePolicy;
+
+/// Describes the `WWW-Authenticate` challenge presented to a client when a
+/// guard rejects a request.
+///
+/// Guards generated by [`scope_guard!`](crate::scope_guard!) build one of
+/// these at compile time from the `realm` configured at the macro call site,
+/// if any.
+#[derive(Debug)]
+pub struct Challenge {
+    realm: &'static str,
+}
+
+impl Challenge {
+    /// Constructs a new challenge with no realm configured.
+    pub const fn new() -> Self {
+        Self { realm: "" }
+    }
+
+    /// Sets the realm reported in the challenge.
+    pub const fn with_realm(mut self, realm: &'static str) -> Self {
+        self.realm = realm;
+        self
+    }
+
+    /// Returns the realm configured for this challenge.
+    pub fn realm(&self) -> &'static str {
+        self.realm
+    }
+}
+
+impl Default for Challenge {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Associates a guard type with the [`ScopePolicy`] it enforces.
+///
+/// This is implemented automatically for guards produced by the scope-set
+/// forms of [`scope_guard!`](crate::scope_guard!) and by
+/// [`scope_guards!`](crate::scope_guards!). The boolean-expression form of
+/// `scope_guard!` implements
+/// [`EndpointScopeExprPolicy`](crate::EndpointScopeExprPolicy) instead.
+pub trait EndpointScopePolicy {
+    /// The claims type extracted and checked by this guard.
+    type Claims;
+
+    /// Returns the scope policy enforced by this guard.
+    fn scope_policy() -> &'static ScopePolicy;
+
+    /// Returns the challenge presented to clients that fail to satisfy
+    /// this guard's requirements.
+    ///
+    /// Defaults to a challenge with no realm configured.
+    fn challenge() -> &'static Challenge {
+        static DEFAULT: Challenge = Challenge::new();
+        &DEFAULT
+    }
+}
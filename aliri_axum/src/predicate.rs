@@ -0,0 +1,238 @@
+//! A guard that layers an arbitrary predicate over claims on top of an
+//! existing guard.
+
+/// Wraps an existing guard with a custom predicate over its claims, run
+/// after the wrapped guard's own check succeeds.
+///
+/// This is useful for authorization rules that aren't expressible as a
+/// scope or role, such as "id must be non-zero" or "tenant in claims equals
+/// tenant in path".
+///
+/// ```
+/// use aliri_axum::{claim_guard, scope_guard};
+///
+/// scope_guard!(AdminOnly(MyClaims); "admin");
+///
+/// claim_guard!(NonAnonymousAdmin(AdminOnly); |claims: MyClaims| claims.id != 0);
+///
+/// # #[derive(Clone)]
+/// # struct MyClaims { id: u64, scope: aliri_oauth2::oauth2::Scope }
+/// # impl aliri_oauth2::oauth2::HasScope for MyClaims {
+/// #     fn scope(&self) -> &aliri_oauth2::oauth2::Scope { &self.scope }
+/// # }
+/// ```
+///
+/// The predicate may also take a second, already-extracted extractor, useful
+/// for rules that compare claims against the request itself:
+///
+/// ```
+/// use aliri_axum::{claim_guard, scope_guard};
+/// use axum::extract::Path;
+///
+/// scope_guard!(AdminOnly(MyClaims); "admin");
+///
+/// claim_guard!(
+///     SameTenant(AdminOnly);
+///     |claims: MyClaims, tenant: Path<String>| claims.tenant == tenant.0
+/// );
+///
+/// # #[derive(Clone)]
+/// # struct MyClaims { tenant: String, scope: aliri_oauth2::oauth2::Scope }
+/// # impl aliri_oauth2::oauth2::HasScope for MyClaims {
+/// #     fn scope(&self) -> &aliri_oauth2::oauth2::Scope { &self.scope }
+/// # }
+/// ```
+///
+/// On rejection, the guard produces [`AuthFailed::PredicateRejected`](crate::AuthFailed::PredicateRejected),
+/// whose `reason` is the guard's name; that name is reported in the
+/// `error_description` of the `WWW-Authenticate` challenge.
+#[macro_export]
+macro_rules! claim_guard {
+    ($vis:vis $i:ident($guard:ty); |$claims:ident : $claims_ty:ty| $body:expr) => {
+        $vis struct $i($vis $guard);
+
+        impl $i {
+            #[allow(dead_code)]
+            $vis fn into_inner(self) -> $guard {
+                self.0
+            }
+
+            #[allow(dead_code)]
+            $vis fn claims(&self) -> &$claims_ty {
+                self.0.claims()
+            }
+        }
+
+        #[::axum::async_trait]
+        impl<B> ::axum::extract::FromRequest<B> for $i
+        where
+            B: Send,
+            $guard: ::axum::extract::FromRequest<B, Rejection = $crate::AuthFailed> + $crate::__private::HasChallenge,
+        {
+            type Rejection = $crate::AuthFailed;
+
+            async fn from_request(
+                req: &mut ::axum::extract::RequestParts<B>,
+            ) -> Result<Self, Self::Rejection> {
+                let inner = <$guard as ::axum::extract::FromRequest<B>>::from_request(req).await?;
+
+                let satisfied = (|$claims: &$claims_ty| -> bool { $body })(inner.claims());
+
+                if satisfied {
+                    Ok(Self(inner))
+                } else {
+                    Err($crate::AuthFailed::PredicateRejected {
+                        reason: ::std::string::String::from(::std::stringify!($i)),
+                        challenge: <$guard as $crate::__private::HasChallenge>::challenge(),
+                    })
+                }
+            }
+        }
+    };
+    ($vis:vis $i:ident($guard:ty); |$claims:ident : $claims_ty:ty, $extra:ident : $extra_ty:ty| $body:expr) => {
+        $vis struct $i($vis $guard);
+
+        impl $i {
+            #[allow(dead_code)]
+            $vis fn into_inner(self) -> $guard {
+                self.0
+            }
+
+            #[allow(dead_code)]
+            $vis fn claims(&self) -> &$claims_ty {
+                self.0.claims()
+            }
+        }
+
+        #[::axum::async_trait]
+        impl<B> ::axum::extract::FromRequest<B> for $i
+        where
+            B: Send,
+            $guard: ::axum::extract::FromRequest<B, Rejection = $crate::AuthFailed> + $crate::__private::HasChallenge,
+            $extra_ty: ::axum::extract::FromRequest<B>,
+            <$extra_ty as ::axum::extract::FromRequest<B>>::Rejection: ::std::fmt::Debug,
+        {
+            type Rejection = $crate::AuthFailed;
+
+            async fn from_request(
+                req: &mut ::axum::extract::RequestParts<B>,
+            ) -> Result<Self, Self::Rejection> {
+                let inner = <$guard as ::axum::extract::FromRequest<B>>::from_request(req).await?;
+
+                let $extra: $extra_ty = ::axum::extract::FromRequest::from_request(req)
+                    .await
+                    .map_err(|err| $crate::AuthFailed::ExtractionFailed {
+                        reason: ::std::format!(
+                            "failed to extract {}: {:?}",
+                            ::std::stringify!($extra_ty),
+                            err,
+                        ),
+                    })?;
+
+                let satisfied =
+                    (|$claims: &$claims_ty, $extra: $extra_ty| -> bool { $body })(inner.claims(), $extra);
+
+                if satisfied {
+                    Ok(Self(inner))
+                } else {
+                    Err($crate::AuthFailed::PredicateRejected {
+                        reason: ::std::string::String::from(::std::stringify!($i)),
+                        challenge: <$guard as $crate::__private::HasChallenge>::challenge(),
+                    })
+                }
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use aliri_oauth2::{oauth2, scope};
+    use axum::{
+        extract::{FromRequest, RequestParts},
+        http::Request,
+    };
+
+    use crate::{scope_guard, AuthFailed};
+
+    scope_guard!(AdminOnly(MyClaims); "admin");
+
+    claim_guard!(NonAnonymousAdmin(AdminOnly); |claims: MyClaims| claims.id != 0);
+
+    #[derive(Clone)]
+    struct MyClaims {
+        id: u64,
+        scope: oauth2::Scope,
+    }
+
+    impl oauth2::HasScope for MyClaims {
+        fn scope(&self) -> &oauth2::Scope {
+            &self.scope
+        }
+    }
+
+    fn request_with(id: u64) -> RequestParts<()> {
+        let mut req = RequestParts::new(Request::new(()));
+        req.extensions_mut().insert(MyClaims {
+            id,
+            scope: scope!["admin"].unwrap(),
+        });
+        req
+    }
+
+    #[tokio::test]
+    async fn non_anonymous_admin_guard_with_non_zero_id() {
+        NonAnonymousAdmin::from_request(&mut request_with(42))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn non_anonymous_admin_guard_with_zero_id() {
+        match NonAnonymousAdmin::from_request(&mut request_with(0)).await {
+            Err(AuthFailed::PredicateRejected { .. }) => {}
+            other => panic!("expected PredicateRejected, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[tokio::test]
+    async fn non_anonymous_admin_guard_without_admin_scope() {
+        let mut req = RequestParts::new(Request::new(()));
+        req.extensions_mut().insert(MyClaims {
+            id: 42,
+            scope: scope![].unwrap(),
+        });
+
+        match NonAnonymousAdmin::from_request(&mut req).await {
+            Err(AuthFailed::InsufficientScopes { .. }) => {}
+            other => panic!("expected InsufficientScopes, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[derive(Debug)]
+    struct AlwaysFailExtraRejection;
+
+    struct AlwaysFailExtra;
+
+    #[axum::async_trait]
+    impl<B: Send> FromRequest<B> for AlwaysFailExtra {
+        type Rejection = AlwaysFailExtraRejection;
+
+        async fn from_request(_req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+            Err(AlwaysFailExtraRejection)
+        }
+    }
+
+    claim_guard!(
+        SameAdmin(AdminOnly);
+        |_claims: MyClaims, _extra: AlwaysFailExtra| true
+    );
+
+    #[tokio::test]
+    async fn same_admin_guard_with_failing_extra_extractor_reports_extraction_failed() {
+        match SameAdmin::from_request(&mut request_with(42)).await {
+            Err(AuthFailed::ExtractionFailed { .. }) => {}
+            other => panic!("expected ExtractionFailed, got {:?}", other.map(|_| ())),
+        }
+    }
+}
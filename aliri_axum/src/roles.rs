@@ -0,0 +1,279 @@
+//! Guards that authorize requests based on group/role membership rather than
+//! OAuth2 scopes.
+
+use crate::Challenge;
+
+/// Claims that report the roles or groups a token's subject belongs to.
+///
+/// Unlike [`HasScope`](aliri_oauth2::oauth2::HasScope), the roles returned
+/// here are the raw values reported by the identity provider, such as
+/// `"warpgate:admin"`, and are translated to internal roles by a
+/// [`RoleMapping`] before being checked against a guard's required roles.
+pub trait HasRoles {
+    /// Returns the raw roles or groups held by this claims object.
+    fn roles(&self) -> &[String];
+}
+
+/// A table mapping raw role/group names reported by an identity provider to
+/// the internal role names used by [`role_guard!`] and [`roles_guards!`].
+///
+/// Raw roles with no entry in the mapping are ignored.
+///
+/// ```
+/// use aliri_axum::RoleMapping;
+///
+/// static WARPGATE_ROLES: RoleMapping = RoleMapping::new(&[
+///     ("warpgate:admin", "admin"),
+///     ("warpgate:user", "user"),
+/// ]);
+/// ```
+#[derive(Debug)]
+pub struct RoleMapping {
+    entries: &'static [(&'static str, &'static str)],
+}
+
+impl RoleMapping {
+    /// Constructs a new mapping from a list of `(raw, internal)` pairs.
+    pub const fn new(entries: &'static [(&'static str, &'static str)]) -> Self {
+        Self { entries }
+    }
+
+    /// Translates a raw role/group name to its internal role, if mapped.
+    pub fn map(&self, raw_role: &str) -> Option<&'static str> {
+        self.entries
+            .iter()
+            .find(|(raw, _)| *raw == raw_role)
+            .map(|(_, internal)| *internal)
+    }
+}
+
+/// Associates a guard type with the roles it requires and the mapping used
+/// to translate raw claim roles into internal ones.
+///
+/// This is implemented automatically for guards produced by [`role_guard!`]
+/// and [`roles_guards!`].
+pub trait EndpointRolePolicy {
+    /// The claims type extracted and checked by this guard.
+    type Claims;
+
+    /// Returns the set of roles, any one of which satisfies this guard.
+    fn required_roles() -> &'static [&'static str];
+
+    /// Returns the mapping used to translate raw claim roles to internal
+    /// roles before checking [`required_roles`](Self::required_roles).
+    fn role_mapping() -> &'static RoleMapping;
+
+    /// Returns the challenge presented to clients that fail this guard's
+    /// requirements.
+    ///
+    /// Defaults to a challenge with no realm configured.
+    fn challenge() -> &'static Challenge {
+        static DEFAULT: Challenge = Challenge::new();
+        &DEFAULT
+    }
+}
+
+/// Constructs an extractor that enables easily asserting that a provided
+/// token belongs to one of a set of roles, as reported by a
+/// [`HasRoles`] claims object and translated through a [`RoleMapping`].
+///
+/// ```
+/// use aliri_axum::role_guard;
+/// use aliri_axum::RoleMapping;
+///
+/// static WARPGATE_ROLES: RoleMapping = RoleMapping::new(&[
+///     ("warpgate:admin", "admin"),
+/// ]);
+///
+/// role_guard!(AdminOnly(MyClaims); ["admin"]; mapping = WARPGATE_ROLES);
+///
+/// # #[derive(Clone)]
+/// # struct MyClaims { roles: Vec<String> }
+/// # impl aliri_axum::HasRoles for MyClaims {
+/// #     fn roles(&self) -> &[String] { &self.roles }
+/// # }
+/// ```
+#[macro_export]
+macro_rules! role_guard {
+    ($vis:vis $i:ident($claim:ty); [$($role:literal),+ $(,)?]; mapping = $mapping:path $(; realm = $realm:literal)?) => {
+        #[doc = "Ensures that a claims object belongs to one of the required roles."]
+        #[doc = ""]
+        #[doc = "Raw roles reported by the claims are translated through the configured mapping "]
+        #[doc = "before being checked against the following roles:"]
+        $(
+            #[doc = concat!("* `", $role, "`")]
+        )+
+        $vis struct $i($vis $claim);
+
+        impl $i {
+            #[allow(dead_code)]
+            $vis fn into_claims(self) -> $claim {
+                self.0
+            }
+
+            #[allow(dead_code)]
+            $vis fn claims(&self) -> &$claim {
+                &self.0
+            }
+        }
+
+        impl $crate::EndpointRolePolicy for $i {
+            type Claims = $claim;
+
+            fn required_roles() -> &'static [&'static str] {
+                &[$($role),+]
+            }
+
+            fn role_mapping() -> &'static $crate::RoleMapping {
+                &$mapping
+            }
+
+            $(
+                fn challenge() -> &'static $crate::Challenge {
+                    static CHALLENGE: $crate::Challenge = $crate::Challenge::new().with_realm($realm);
+                    &CHALLENGE
+                }
+            )?
+        }
+
+        impl $crate::__private::HasChallenge for $i {
+            fn challenge() -> &'static $crate::Challenge {
+                <Self as $crate::EndpointRolePolicy>::challenge()
+            }
+        }
+
+        #[::axum::async_trait]
+        impl<B> ::axum::extract::FromRequest<B> for $i
+        where
+            B: Send,
+        {
+            type Rejection = $crate::AuthFailed;
+
+            async fn from_request(
+                req: &mut ::axum::extract::RequestParts<B>,
+            ) -> Result<Self, Self::Rejection> {
+                $crate::__private::from_request_roles(
+                    req,
+                    <Self as $crate::EndpointRolePolicy>::required_roles(),
+                    <Self as $crate::EndpointRolePolicy>::role_mapping(),
+                    <Self as $crate::EndpointRolePolicy>::challenge(),
+                )
+                .map(Self)
+            }
+        }
+    };
+}
+
+/// Convenience macro for services that need to define many role guards
+/// against the same mapping. Analogous to [`scope_guards!`](crate::scope_guards!).
+///
+/// ```
+/// use aliri_axum::{roles_guards, RoleMapping};
+///
+/// static WARPGATE_ROLES: RoleMapping = RoleMapping::new(&[
+///     ("warpgate:admin", "admin"),
+///     ("warpgate:user", "user"),
+/// ]);
+///
+/// roles_guards! {
+///     type Claims = MyClaims;
+///     mapping = WARPGATE_ROLES;
+///
+///     role AdminOnly = ["admin"];
+///     role UserOrAdmin = ["admin", "user"];
+/// }
+///
+/// # #[derive(Clone)]
+/// # struct MyClaims { roles: Vec<String> }
+/// # impl aliri_axum::HasRoles for MyClaims {
+/// #     fn roles(&self) -> &[String] { &self.roles }
+/// # }
+/// ```
+#[macro_export]
+macro_rules! roles_guards {
+    (type Claims = $claims:ty; mapping = $mapping:path; $($vis:vis role $i:ident = [$($role:literal),+ $(,)?]);* $(;)?) => {
+        $(
+            $crate::role_guard!($vis $i($claims); [$($role),+]; mapping = $mapping);
+        )*
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        extract::{FromRequest, RequestParts},
+        http::Request,
+    };
+
+    use crate::AuthFailed;
+
+    static WARPGATE_ROLES: crate::RoleMapping = crate::RoleMapping::new(&[
+        ("warpgate:admin", "admin"),
+        ("warpgate:user", "user"),
+    ]);
+
+    roles_guards! {
+        type Claims = MyClaims;
+        mapping = WARPGATE_ROLES;
+
+        role AdminOnly = ["admin"];
+        role UserOrAdmin = ["admin", "user"];
+    }
+
+    #[derive(Clone)]
+    struct MyClaims {
+        roles: Vec<String>,
+    }
+
+    impl super::HasRoles for MyClaims {
+        fn roles(&self) -> &[String] {
+            &self.roles
+        }
+    }
+
+    fn request_with_roles(roles: &[&str]) -> RequestParts<()> {
+        let mut req = RequestParts::new(Request::new(()));
+        req.extensions_mut().insert(MyClaims {
+            roles: roles.iter().map(|r| r.to_string()).collect(),
+        });
+        req
+    }
+
+    #[tokio::test]
+    async fn admin_only_guard_with_mapped_admin_role() {
+        AdminOnly::from_request(&mut request_with_roles(&["warpgate:admin"]))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn admin_only_guard_with_unmapped_role() {
+        match AdminOnly::from_request(&mut request_with_roles(&["some:other:role"])).await {
+            Err(AuthFailed::InsufficientRoles { .. }) => {}
+            other => panic!("expected InsufficientRoles, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[tokio::test]
+    async fn admin_only_guard_with_mapped_user_role() {
+        match AdminOnly::from_request(&mut request_with_roles(&["warpgate:user"])).await {
+            Err(AuthFailed::InsufficientRoles { .. }) => {}
+            other => panic!("expected InsufficientRoles, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[tokio::test]
+    async fn user_or_admin_guard_with_mapped_user_role() {
+        UserOrAdmin::from_request(&mut request_with_roles(&["warpgate:user"]))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn admin_only_guard_without_claims() {
+        match AdminOnly::from_request(&mut RequestParts::new(Request::new(()))).await {
+            Err(AuthFailed::MissingClaims { .. }) => {}
+            other => panic!("expected MissingClaims, got {:?}", other.map(|_| ())),
+        }
+    }
+}
@@ -0,0 +1,241 @@
+//! Procedural macro backing the boolean scope-expression grammar of
+//! `aliri_axum::scope_guard!`.
+//!
+//! This crate is an implementation detail of `aliri_axum` and is not meant
+//! to be depended on directly.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::{parse_macro_input, Ident, LitStr, Token, Type, Visibility};
+
+/// A boolean expression over scope literals:
+///
+/// ```text
+/// expr  := or
+/// or    := and ("||" and)*
+/// and   := unary ("&&" unary)*
+/// unary := "!" unary | atom
+/// atom  := LIT_STR | "(" expr ")"
+/// ```
+enum Expr {
+    Literal(LitStr),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+impl Parse for Expr {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        parse_or(input)
+    }
+}
+
+fn parse_or(input: ParseStream) -> syn::Result<Expr> {
+    let mut lhs = parse_and(input)?;
+    while input.peek(Token![||]) {
+        input.parse::<Token![||]>()?;
+        let rhs = parse_and(input)?;
+        lhs = Expr::Or(Box::new(lhs), Box::new(rhs));
+    }
+    Ok(lhs)
+}
+
+fn parse_and(input: ParseStream) -> syn::Result<Expr> {
+    let mut lhs = parse_unary(input)?;
+    while input.peek(Token![&&]) {
+        input.parse::<Token![&&]>()?;
+        let rhs = parse_unary(input)?;
+        lhs = Expr::And(Box::new(lhs), Box::new(rhs));
+    }
+    Ok(lhs)
+}
+
+fn parse_unary(input: ParseStream) -> syn::Result<Expr> {
+    if input.peek(Token![!]) {
+        input.parse::<Token![!]>()?;
+        Ok(Expr::Not(Box::new(parse_unary(input)?)))
+    } else {
+        parse_atom(input)
+    }
+}
+
+fn parse_atom(input: ParseStream) -> syn::Result<Expr> {
+    if input.peek(syn::token::Paren) {
+        let content;
+        syn::parenthesized!(content in input);
+        content.parse()
+    } else {
+        let lit: LitStr = input.parse()?;
+        Ok(literal_to_expr(lit))
+    }
+}
+
+/// Splits a space-jammed literal like `"delete:profile admin"` into an
+/// implicit `And` chain of its individual tokens, matching the meaning that
+/// same literal has in the simpler DNF forms of `scope_guard!`, where it's
+/// parsed by `ScopePolicy` as a set of scopes that must all be held.
+fn literal_to_expr(lit: LitStr) -> Expr {
+    let words: Vec<String> = lit.value().split_whitespace().map(str::to_owned).collect();
+    match words.len() {
+        0 | 1 => Expr::Literal(lit),
+        _ => {
+            let mut words = words.into_iter();
+            let first = words.next().expect("checked len > 1");
+            let mut expr = Expr::Literal(LitStr::new(&first, lit.span()));
+            for word in words {
+                let next = Expr::Literal(LitStr::new(&word, lit.span()));
+                expr = Expr::And(Box::new(expr), Box::new(next));
+            }
+            expr
+        }
+    }
+}
+
+impl Expr {
+    fn to_runtime(&self) -> TokenStream2 {
+        match self {
+            Self::Literal(lit) => quote! {
+                &::aliri_axum::__private::ScopeExpr::Literal(#lit)
+            },
+            Self::Not(inner) => {
+                let inner = inner.to_runtime();
+                quote! { &::aliri_axum::__private::ScopeExpr::Not(#inner) }
+            }
+            Self::And(lhs, rhs) => {
+                let lhs = lhs.to_runtime();
+                let rhs = rhs.to_runtime();
+                quote! { &::aliri_axum::__private::ScopeExpr::And(#lhs, #rhs) }
+            }
+            Self::Or(lhs, rhs) => {
+                let lhs = lhs.to_runtime();
+                let rhs = rhs.to_runtime();
+                quote! { &::aliri_axum::__private::ScopeExpr::Or(#lhs, #rhs) }
+            }
+        }
+    }
+}
+
+struct ScopeExprGuard {
+    vis: Visibility,
+    ident: Ident,
+    claim: Type,
+    expr: Expr,
+    realm: Option<LitStr>,
+}
+
+impl Parse for ScopeExprGuard {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let vis = input.parse()?;
+        let ident = input.parse()?;
+
+        let content;
+        syn::parenthesized!(content in input);
+        let claim = content.parse()?;
+
+        input.parse::<Token![;]>()?;
+        let expr = input.parse()?;
+
+        let realm = if input.peek(Token![;]) {
+            input.parse::<Token![;]>()?;
+            let kw: Ident = input.parse()?;
+            if kw != "realm" {
+                return Err(syn::Error::new(kw.span(), "expected `realm`"));
+            }
+            input.parse::<Token![=]>()?;
+            Some(input.parse()?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            vis,
+            ident,
+            claim,
+            expr,
+            realm,
+        })
+    }
+}
+
+/// Expands the boolean-expression form of `scope_guard!`.
+///
+/// Not meant to be invoked directly; use `aliri_axum::scope_guard!` instead,
+/// which forwards to this macro when the scope grammar it's given doesn't
+/// fit the simpler, `macro_rules!`-driven disjunctive-normal-form grammar.
+#[proc_macro]
+pub fn scope_expr_guard(input: TokenStream) -> TokenStream {
+    let ScopeExprGuard {
+        vis,
+        ident,
+        claim,
+        expr,
+        realm,
+    } = parse_macro_input!(input as ScopeExprGuard);
+
+    let expr_tokens = expr.to_runtime();
+
+    let challenge_impl = realm.map(|realm| {
+        quote! {
+            fn challenge() -> &'static ::aliri_axum::Challenge {
+                static CHALLENGE: ::aliri_axum::Challenge =
+                    ::aliri_axum::Challenge::new().with_realm(#realm);
+                &CHALLENGE
+            }
+        }
+    });
+
+    let expanded = quote! {
+        #vis struct #ident(#vis #claim);
+
+        impl #ident {
+            #[allow(dead_code)]
+            #vis fn into_claims(self) -> #claim {
+                self.0
+            }
+
+            #[allow(dead_code)]
+            #vis fn claims(&self) -> &#claim {
+                &self.0
+            }
+        }
+
+        impl ::aliri_axum::__private::EndpointScopeExprPolicy for #ident {
+            type Claims = #claim;
+
+            fn scope_expr() -> &'static ::aliri_axum::__private::ScopeExpr {
+                #expr_tokens
+            }
+
+            #challenge_impl
+        }
+
+        impl ::aliri_axum::__private::HasChallenge for #ident {
+            fn challenge() -> &'static ::aliri_axum::Challenge {
+                <Self as ::aliri_axum::__private::EndpointScopeExprPolicy>::challenge()
+            }
+        }
+
+        #[::axum::async_trait]
+        impl<B> ::axum::extract::FromRequest<B> for #ident
+        where
+            B: Send,
+        {
+            type Rejection = ::aliri_axum::AuthFailed;
+
+            async fn from_request(
+                req: &mut ::axum::extract::RequestParts<B>,
+            ) -> Result<Self, Self::Rejection> {
+                ::aliri_axum::__private::from_request_expr(
+                    req,
+                    <Self as ::aliri_axum::__private::EndpointScopeExprPolicy>::scope_expr(),
+                    <Self as ::aliri_axum::__private::EndpointScopeExprPolicy>::challenge(),
+                )
+                .map(Self)
+            }
+        }
+    };
+
+    expanded.into()
+}